@@ -0,0 +1,305 @@
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead};
+
+// Custom deserialization for "Yes"/"No" string to Option<bool>
+fn deserialize_yes_no<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: Option<String> = Option::deserialize(deserializer)?;
+    match s {
+        None => Ok(None),
+        Some(s) => match s.as_str() {
+            "Yes" | "yes" | "YES" | "true" | "TRUE" | "True" | "1" => Ok(Some(true)),
+            "No" | "no" | "NO" | "false" | "FALSE" | "False" | "0" => Ok(Some(false)),
+            _ => Ok(None)
+        },
+    }
+}
+
+// Parses a timestamp string using the given assumed source timezone for the
+// naive (no-offset) formats. Not a serde `deserialize_with` function: those
+// can't take extra arguments, so callers that care about the source timezone
+// go through `parse_line_with_source_tz` / `MessageStream::with_source_tz`
+// instead, which deserialize `time` as a raw string and call this explicitly.
+fn parse_timestamp(time_str: Option<&str>, source_tz: FixedOffset) -> DateTime<Utc> {
+    let time_str = match time_str {
+        None => return Utc::now(),
+        Some(time_str) => time_str,
+    };
+
+    // Handle multiple possible date formats from rtl_433
+
+    // Format: "2023-04-15 14:32:56" (most common). Naive, so it's
+    // localized in the assumed source timezone before converting to UTC.
+    if let Ok(naive_time) = NaiveDateTime::parse_from_str(time_str, "%Y-%m-%d %H:%M:%S") {
+        return source_tz
+            .from_local_datetime(&naive_time)
+            .single()
+            .unwrap_or_else(|| Utc::now().into())
+            .with_timezone(&Utc);
+    }
+
+    // Format with fractional seconds: "2023-04-15 14:32:56.123"
+    if let Ok(naive_time) = NaiveDateTime::parse_from_str(time_str, "%Y-%m-%d %H:%M:%S%.f") {
+        return source_tz
+            .from_local_datetime(&naive_time)
+            .single()
+            .unwrap_or_else(|| Utc::now().into())
+            .with_timezone(&Utc);
+    }
+
+    // ISO 8601 format: "2023-04-15T14:32:56Z"
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(time_str) {
+        return datetime.with_timezone(&Utc);
+    }
+
+    // Unix timestamp, unit auto-detected from magnitude: seconds,
+    // milliseconds, microseconds, or nanoseconds since epoch.
+    if let Ok(timestamp) = time_str.parse::<i64>() {
+        // `unsigned_abs` rather than `abs`: `abs` panics on `i64::MIN`, which
+        // is a valid (if absurd) value to find in this field.
+        let abs = timestamp.unsigned_abs();
+        let parsed = if abs < 100_000_000_000 {
+            // seconds
+            Utc.timestamp_opt(timestamp, 0).single()
+        } else if abs < 100_000_000_000_000 {
+            // milliseconds
+            Utc.timestamp_millis_opt(timestamp).single()
+        } else if abs < 100_000_000_000_000_000 {
+            // microseconds
+            Utc.timestamp_micros(timestamp).single()
+        } else {
+            // nanoseconds, split into secs + subsec nanos
+            let secs = timestamp.div_euclid(1_000_000_000);
+            let subsec_nanos = timestamp.rem_euclid(1_000_000_000) as u32;
+            Utc.timestamp_opt(secs, subsec_nanos).single()
+        };
+        return parsed.unwrap_or_else(Utc::now);
+    }
+
+    // If none of the formats match, return the current time as fallback
+    eprintln!("Unknown timestamp format: {}", time_str);
+    Utc::now()
+}
+
+// Define flexible structures to handle various rtl_433 output formats
+#[derive(Debug, Serialize)]
+pub struct RTL433Message {
+    // Common fields often found in rtl_433 JSON output
+    pub time: DateTime<Utc>,
+    pub model: String,
+    pub id: Option<i64>,
+    pub channel: Option<i64>,
+    #[serde(rename = "temperature_C")]
+    pub temperature_c: Option<f64>,
+    pub humidity: Option<i64>,
+    pub battery_ok: Option<f64>,
+    pub test: Option<bool>,
+    pub mic: String, // Integrity
+    // Catch-all for the hundreds of device-specific fields rtl_433 can emit
+    // (wind speed, rain totals, pressure, contact state, raw codes, ...)
+    // that aren't worth hard-coding a field for.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+// Deserializing an `RTL433Message` directly (e.g. `serde_json::from_str`)
+// goes through the same `RawRTL433Message` -> `parse_timestamp` path as
+// `parse_line`, assuming UTC for naive timestamps, so there's exactly one
+// place that interprets the `time` field instead of two that could drift
+// apart. Use `parse_line_with_source_tz` / `MessageStream::with_source_tz`
+// to override the assumed timezone.
+impl<'de> Deserialize<'de> for RTL433Message {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = RawRTL433Message::deserialize(deserializer)?;
+        Ok(raw.into_message(FixedOffset::east_opt(0).unwrap()))
+    }
+}
+
+// Mirrors `RTL433Message` field-for-field except `time`, which is kept as the
+// raw string so `parse_line_with_source_tz` can localize it explicitly
+// instead of baking an assumed timezone into the `Deserialize` impl.
+#[derive(Debug, Deserialize)]
+struct RawRTL433Message {
+    #[serde(default)]
+    time: Option<String>,
+    #[serde(default)]
+    model: String,
+    #[serde(default)]
+    id: Option<i64>,
+    #[serde(default)]
+    channel: Option<i64>,
+    #[serde(default, rename = "temperature_C")]
+    temperature_c: Option<f64>,
+    #[serde(default)]
+    humidity: Option<i64>,
+    #[serde(default)]
+    battery_ok: Option<f64>,
+    #[serde(default, deserialize_with = "deserialize_yes_no")]
+    test: Option<bool>,
+    #[serde(default)]
+    mic: String,
+    #[serde(flatten)]
+    extra: HashMap<String, serde_json::Value>,
+}
+
+impl RawRTL433Message {
+    fn into_message(self, source_tz: FixedOffset) -> RTL433Message {
+        RTL433Message {
+            time: parse_timestamp(self.time.as_deref(), source_tz),
+            model: self.model,
+            id: self.id,
+            channel: self.channel,
+            temperature_c: self.temperature_c,
+            humidity: self.humidity,
+            battery_ok: self.battery_ok,
+            test: self.test,
+            mic: self.mic,
+            extra: self.extra,
+        }
+    }
+}
+
+/// Parses a single line of rtl_433 JSON output into an [`RTL433Message`],
+/// assuming UTC for any naive (no-offset) timestamp. Use
+/// [`parse_line_with_source_tz`] when the source is known to emit wall-clock
+/// time in a different zone (e.g. rtl_433 run without `-M time:utc`).
+pub fn parse_line(line: &str) -> Result<RTL433Message, serde_json::Error> {
+    parse_line_with_source_tz(line, FixedOffset::east_opt(0).unwrap())
+}
+
+/// Parses a single line of rtl_433 JSON output into an [`RTL433Message`],
+/// localizing any naive (no-offset) timestamp in `source_tz` before
+/// converting it to UTC.
+pub fn parse_line_with_source_tz(
+    line: &str,
+    source_tz: FixedOffset,
+) -> Result<RTL433Message, serde_json::Error> {
+    let raw: RawRTL433Message = serde_json::from_str(line)?;
+    Ok(raw.into_message(source_tz))
+}
+
+/// A JSON line that failed to parse into an [`RTL433Message`], together with
+/// the raw line it came from so callers can log it for debugging.
+#[derive(Debug)]
+pub struct ParseLineError {
+    pub line: String,
+    pub source: serde_json::Error,
+}
+
+impl fmt::Display for ParseLineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (raw line: {})", self.source, self.line)
+    }
+}
+
+impl std::error::Error for ParseLineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Streams [`RTL433Message`]s out of any line-buffered reader, e.g. stdin or
+/// the stdout of a spawned `rtl_433` process. Lines that fail to parse as
+/// JSON are yielded as `Err`, carrying the raw line alongside the
+/// `serde_json::Error`; lines that fail to even be read (an I/O error) are
+/// logged to stderr and skipped, matching rtl_433's own tolerance for the
+/// occasional corrupted line on a noisy radio channel.
+pub struct MessageStream<R: BufRead> {
+    lines: io::Lines<R>,
+    source_tz: FixedOffset,
+}
+
+impl<R: BufRead> MessageStream<R> {
+    /// Assumes UTC for any naive (no-offset) timestamp. Use
+    /// [`MessageStream::with_source_tz`] to override that.
+    pub fn new(reader: R) -> Self {
+        MessageStream::with_source_tz(reader, FixedOffset::east_opt(0).unwrap())
+    }
+
+    pub fn with_source_tz(reader: R, source_tz: FixedOffset) -> Self {
+        MessageStream {
+            lines: reader.lines(),
+            source_tz,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for MessageStream<R> {
+    type Item = Result<RTL433Message, ParseLineError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.lines.next()? {
+                Ok(line) => {
+                    return Some(
+                        parse_line_with_source_tz(&line, self.source_tz)
+                            .map_err(|source| ParseLineError { line, source }),
+                    )
+                }
+                Err(e) => eprintln!("Error reading line: {}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_with_time(time: &str) -> String {
+        format!(r#"{{"time":"{}","model":"Test"}}"#, time)
+    }
+
+    #[test]
+    fn seconds_millis_micros_nanos_agree_on_the_same_instant() {
+        let expected = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+
+        let seconds = parse_line(&line_with_time("1700000000")).unwrap();
+        let millis = parse_line(&line_with_time("1700000000000")).unwrap();
+        let micros = parse_line(&line_with_time("1700000000000000")).unwrap();
+        let nanos = parse_line(&line_with_time("1700000000000000000")).unwrap();
+
+        assert_eq!(seconds.time, expected);
+        assert_eq!(millis.time, expected);
+        assert_eq!(micros.time, expected);
+        assert_eq!(nanos.time, expected);
+    }
+
+    #[test]
+    fn unit_boundaries_are_resolved_correctly() {
+        // Just below 1e11 is still seconds.
+        let message = parse_line(&line_with_time("99999999999")).unwrap();
+        assert_eq!(message.time, Utc.timestamp_opt(99_999_999_999, 0).unwrap());
+
+        // 1e11 itself switches to milliseconds.
+        let message = parse_line(&line_with_time("100000000000")).unwrap();
+        assert_eq!(message.time, Utc.timestamp_millis_opt(100_000_000_000).unwrap());
+
+        // 1e14 switches to microseconds.
+        let message = parse_line(&line_with_time("100000000000000")).unwrap();
+        assert_eq!(message.time, Utc.timestamp_micros(100_000_000_000_000).unwrap());
+
+        // 1e17 switches to nanoseconds.
+        let message = parse_line(&line_with_time("100000000000000000")).unwrap();
+        assert_eq!(message.time, Utc.timestamp_opt(100_000_000, 0).unwrap());
+    }
+
+    #[test]
+    fn negative_epoch_values_do_not_panic() {
+        // A very large negative value falls into the nanoseconds branch.
+        let message = parse_line(&line_with_time("-9223372036854775808")).unwrap();
+        assert!(message.time.timestamp() < 0);
+
+        // An ordinary negative value (before 1970) is treated as seconds.
+        let message = parse_line(&line_with_time("-100000")).unwrap();
+        assert_eq!(message.time, Utc.timestamp_opt(-100_000, 0).unwrap());
+    }
+}
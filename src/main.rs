@@ -1,166 +1,203 @@
-use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
-use serde::{Deserialize, Deserializer, Serialize};
+use chrono::{DateTime, FixedOffset, Utc};
+use rtl_433_data_collector::{MessageStream, RTL433Message};
 use std::error::Error;
 use std::io::{self, BufRead};
 use std::process::{Command, Stdio};
 
-// Custom deserialization for "Yes"/"No" string to Option<bool>
-fn deserialize_yes_no<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s: Option<String> = Option::deserialize(deserializer)?;
-    match s {
-        None => Ok(None),
-        Some(s) => match s.as_str() {
-            "Yes" | "yes" | "YES" | "true" | "TRUE" | "True" | "1" => Ok(Some(true)),
-            "No" | "no" | "NO" | "false" | "FALSE" | "False" | "0" => Ok(Some(false)),
-            _ => Ok(None)
-        },
+// Returns the value following `flag` in the process's CLI args, if present,
+// e.g. `scan_arg_value("--time-format")` for `--time-format rfc2822`.
+fn scan_arg_value(flag: &str) -> Option<String> {
+    let mut args = std::env::args();
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next();
+        }
     }
+    None
 }
 
-// Custom deserializer for timestamp strings
-fn deserialize_timestamp<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s: Option<String> = Option::deserialize(deserializer)?;
-    
-    match s {
-        None => Ok(Utc::now()),
-        Some(time_str) => {
-            // Handle multiple possible date formats from rtl_433
-            
-            // Format: "2023-04-15 14:32:56" (most common)
-            if let Ok(naive_time) = NaiveDateTime::parse_from_str(&time_str, "%Y-%m-%d %H:%M:%S") {
-                return Ok(Utc.from_utc_datetime(&naive_time));
-            }
-            
-            // Format with fractional seconds: "2023-04-15 14:32:56.123"
-            if let Ok(naive_time) = NaiveDateTime::parse_from_str(&time_str, "%Y-%m-%d %H:%M:%S%.f") {
-                return Ok(Utc.from_utc_datetime(&naive_time));
-            }
-            
-            // ISO 8601 format: "2023-04-15T14:32:56Z"
-            if let Ok(datetime) = DateTime::parse_from_rfc3339(&time_str) {
-                return Ok(datetime.with_timezone(&Utc));
+// Parses a fixed UTC offset from a string like "+02:00", "-05:30", "Z", or "UTC".
+fn parse_fixed_offset(s: &str) -> Option<FixedOffset> {
+    if s.eq_ignore_ascii_case("UTC") || s == "Z" {
+        return FixedOffset::east_opt(0);
+    }
+
+    let (sign, rest) = match s.as_bytes().first()? {
+        b'+' => (1, &s[1..]),
+        b'-' => (-1, &s[1..]),
+        _ => return None,
+    };
+
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((h, m)) => (h.parse::<i32>().ok()?, m.parse::<i32>().ok()?),
+        None => (rest.parse::<i32>().ok()?, 0),
+    };
+
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+// Determined from `--source-tz <offset>` if present, else the
+// `RTL433_SOURCE_TZ` env var, else UTC.
+fn source_tz_from_args_or_env() -> FixedOffset {
+    if let Some(value) = scan_arg_value("--source-tz") {
+        if let Some(offset) = parse_fixed_offset(&value) {
+            return offset;
+        }
+        eprintln!("Unknown --source-tz value: {}", value);
+    }
+
+    if let Ok(value) = std::env::var("RTL433_SOURCE_TZ") {
+        if let Some(offset) = parse_fixed_offset(&value) {
+            return offset;
+        }
+        eprintln!("Unknown RTL433_SOURCE_TZ value: {}", value);
+    }
+
+    FixedOffset::east_opt(0).unwrap()
+}
+
+// Output time encoding, selectable via the `--time-format` CLI flag or the
+// `RTL433_TIME_FORMAT` env var. Mirrors the handful of canonical encodings
+// downstream consumers typically expect instead of chrono's default `Display`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeFormat {
+    Rfc3339,
+    Rfc2822,
+    Iso8601,
+    UnixSeconds,
+    UnixMillis,
+}
+
+impl TimeFormat {
+    fn from_str(s: &str) -> Option<TimeFormat> {
+        match s.to_ascii_lowercase().as_str() {
+            "rfc3339" => Some(TimeFormat::Rfc3339),
+            "rfc2822" => Some(TimeFormat::Rfc2822),
+            "iso8601" => Some(TimeFormat::Iso8601),
+            "unix" | "unix_seconds" | "unix-seconds" => Some(TimeFormat::UnixSeconds),
+            "unix_millis" | "unix-millis" => Some(TimeFormat::UnixMillis),
+            _ => None,
+        }
+    }
+
+    // Determined from `--time-format <value>` if present, else the
+    // `RTL433_TIME_FORMAT` env var, else the RFC3339 default.
+    fn from_args_or_env() -> TimeFormat {
+        if let Some(value) = scan_arg_value("--time-format") {
+            if let Some(format) = TimeFormat::from_str(&value) {
+                return format;
             }
-            
-            // Unix timestamp (seconds since epoch)
-            if let Ok(timestamp) = time_str.parse::<i64>() {
-                return Ok(Utc.timestamp_opt(timestamp, 0).single().unwrap_or_else(|| Utc::now()));
+            eprintln!("Unknown --time-format value: {}", value);
+        }
+
+        if let Ok(value) = std::env::var("RTL433_TIME_FORMAT") {
+            if let Some(format) = TimeFormat::from_str(&value) {
+                return format;
             }
-            
-            // If none of the formats match, return the current time as fallback
-            eprintln!("Unknown timestamp format: {}", time_str);
-            Ok(Utc::now())
+            eprintln!("Unknown RTL433_TIME_FORMAT value: {}", value);
         }
+
+        TimeFormat::Rfc3339
     }
 }
 
-// Define flexible structures to handle various rtl_433 output formats
-#[derive(Debug, Deserialize, Serialize)]
-struct RTL433Message {
-    // Common fields often found in rtl_433 JSON output
-    #[serde(default, deserialize_with = "deserialize_timestamp")]
-    time: DateTime<Utc>,
-    #[serde(default)]
-    model: String,
-    #[serde(default)]
-    id: Option<i64>,
-    #[serde(default)]
-    channel: Option<i64>,
-    #[serde(default, rename = "temperature_C")]
-    temperature_c: Option<f64>,
-    #[serde(default)]
-    humidity: Option<i64>,
-    #[serde(default)]
-    battery_ok: Option<f64>,
-    #[serde(default, deserialize_with = "deserialize_yes_no")]
-    test: Option<bool>,
-    #[serde(default)]
-    mic: String, // Integrity
+fn format_time(time: &DateTime<Utc>, format: TimeFormat) -> String {
+    match format {
+        TimeFormat::Rfc3339 => time.to_rfc3339(),
+        TimeFormat::Rfc2822 => time.to_rfc2822(),
+        TimeFormat::Iso8601 => time.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string(),
+        TimeFormat::UnixSeconds => time.timestamp().to_string(),
+        TimeFormat::UnixMillis => time.timestamp_millis().to_string(),
+    }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     println!("RTL-433 Parser starting...");
 
+    let time_format = TimeFormat::from_args_or_env();
+    let source_tz = source_tz_from_args_or_env();
+
     // Two options for getting rtl_433 data:
     // 1. Execute rtl_433 and capture its output
     if cfg!(feature = "execute_rtl433") {
-        parse_from_rtl433_process()?;
-    } 
+        parse_from_rtl433_process(time_format)?;
+    }
     // 2. Read from stdin (for piping: rtl_433 -F json | your_program)
     else {
-        parse_from_stdin()?;
+        parse_from_stdin(time_format, source_tz)?;
     }
 
     Ok(())
 }
 
-fn parse_from_rtl433_process() -> Result<(), Box<dyn Error>> {
+fn parse_from_rtl433_process(time_format: TimeFormat) -> Result<(), Box<dyn Error>> {
     // Start rtl_433 process with JSON output
     let mut child = Command::new("rtl_433")
         .args(["-F", "json", "-M", "time:utc"])
         .stdout(Stdio::piped())
         .spawn()?;
-    
+
     let stdout = child.stdout.take().expect("Failed to open stdout");
-    let reader = io::BufReader::new(stdout);
-    
-    for line in reader.lines() {
-        match line {
-            Ok(json_line) => process_json_line(&json_line)?,
-            Err(e) => eprintln!("Error reading line: {}", e),
-        }
-    }
-    
+    // Spawned with `-M time:utc` above, so its naive timestamps are always
+    // UTC already; `--source-tz` only applies to the stdin path, where the
+    // source process isn't under our control.
+    run(
+        io::BufReader::new(stdout),
+        time_format,
+        FixedOffset::east_opt(0).unwrap(),
+    );
+
     Ok(())
 }
 
-fn parse_from_stdin() -> Result<(), Box<dyn Error>> {
+fn parse_from_stdin(time_format: TimeFormat, source_tz: FixedOffset) -> Result<(), Box<dyn Error>> {
     let stdin = io::stdin();
-    let reader = stdin.lock();
-    
-    for line in reader.lines() {
-        match line {
-            Ok(json_line) => process_json_line(&json_line)?,
-            Err(e) => eprintln!("Error reading line: {}", e),
-        }
-    }
-    
+    run(stdin.lock(), time_format, source_tz);
+
     Ok(())
 }
 
-fn process_json_line(json_line: &str) -> Result<(), Box<dyn Error>> {
-    // Parse JSON
-    match serde_json::from_str::<RTL433Message>(json_line) {
-        Ok(message) => {
-            println!("Received message from model: {} at {}", message.model, message.time);
-            
-            // Print temperature if available
-            if let Some(temp) = message.temperature_c {
-                println!("  Temperature: {:.1}Â°C", temp);
+// Drives a `MessageStream` over any line-buffered reader, printing each
+// successfully decoded message. Shared by both the stdin and spawned-process
+// entry points so the read loop only needs to exist once.
+fn run<R: BufRead>(reader: R, time_format: TimeFormat, source_tz: FixedOffset) {
+    for result in MessageStream::with_source_tz(reader, source_tz) {
+        match result {
+            Ok(message) => print_message(&message, time_format),
+            Err(e) => {
+                eprintln!("Failed to parse JSON: {}", e.source);
+                eprintln!("Raw line: {}", e.line);
             }
-            
-            // Print humidity if available
-            if let Some(humidity) = message.humidity {
-                println!("  Humidity: {}%", humidity);
-            }
-            
-            // Print battery status if available
-            if let Some(test) = message.test {
-                println!("  Is test: {}", test);
-            }
-            
-            println!(""); // Empty line for readability
-        },
-        Err(e) => {
-            eprintln!("Failed to parse JSON: {}", e);
-            eprintln!("Raw line: {}", json_line);
         }
     }
-    
-    Ok(())
-}
\ No newline at end of file
+}
+
+fn print_message(message: &RTL433Message, time_format: TimeFormat) {
+    println!(
+        "Received message from model: {} at {}",
+        message.model,
+        format_time(&message.time, time_format)
+    );
+
+    // Print temperature if available
+    if let Some(temp) = message.temperature_c {
+        println!("  Temperature: {:.1}Â°C", temp);
+    }
+
+    // Print humidity if available
+    if let Some(humidity) = message.humidity {
+        println!("  Humidity: {}%", humidity);
+    }
+
+    // Print battery status if available
+    if let Some(test) = message.test {
+        println!("  Is test: {}", test);
+    }
+
+    // Print any device-specific fields we don't have dedicated struct fields for
+    for (key, value) in &message.extra {
+        println!("  {}: {}", key, value);
+    }
+
+    println!(); // Empty line for readability
+}